@@ -1,8 +1,494 @@
+use std::time::{Duration, Instant};
 use wgpu::{util::DeviceExt, BufferUsages};
 use winit::{
-    dpi::PhysicalPosition, event::{Event, KeyEvent, WindowEvent}, event_loop::EventLoop, keyboard::{KeyCode, PhysicalKey}, window::{Window, WindowBuilder}
+    event::{Event, KeyEvent, WindowEvent}, event_loop::{ControlFlow, EventLoop}, keyboard::{KeyCode, PhysicalKey}, window::{Window, WindowBuilder}
 };
 
+// Wraps a wgpu texture together with the view and sampler used to read it in a shader
+// Keeping the three together means callers never forget to create one of them
+mod texture {
+    use image::GenericImageView;
+
+    pub struct Texture {
+        pub texture: wgpu::Texture,
+        pub view: wgpu::TextureView,
+        pub sampler: wgpu::Sampler,
+    }
+
+    impl Texture {
+        // Decodes an in-memory image (PNG/JPEG/etc, whatever the `image` crate supports)
+        // and uploads it to the GPU as an RGBA8 texture
+        pub fn from_bytes(device: &wgpu::Device, queue: &wgpu::Queue, bytes: &[u8], label: &str) -> Result<Self, image::ImageError> {
+            let img = image::load_from_memory(bytes)?;
+            Ok(Self::from_image(device, queue, &img, Some(label)))
+        }
+
+        pub fn from_image(device: &wgpu::Device, queue: &wgpu::Queue, img: &image::DynamicImage, label: Option<&str>) -> Self {
+            let rgba = img.to_rgba8();
+            let dimensions = img.dimensions();
+
+            let size = wgpu::Extent3d {
+                width: dimensions.0,
+                height: dimensions.1,
+                depth_or_array_layers: 1,
+            };
+
+            let texture = device.create_texture(&wgpu::TextureDescriptor {
+                label,
+                size,
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: wgpu::TextureFormat::Rgba8UnormSrgb,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+                view_formats: &[],
+            });
+
+            queue.write_texture(
+                wgpu::ImageCopyTexture {
+                    texture: &texture,
+                    mip_level: 0,
+                    origin: wgpu::Origin3d::ZERO,
+                    aspect: wgpu::TextureAspect::All,
+                },
+                &rgba,
+                wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(4 * dimensions.0),
+                    rows_per_image: Some(dimensions.1),
+                },
+                size,
+            );
+
+            let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+            let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+                address_mode_u: wgpu::AddressMode::ClampToEdge,
+                address_mode_v: wgpu::AddressMode::ClampToEdge,
+                address_mode_w: wgpu::AddressMode::ClampToEdge,
+                mag_filter: wgpu::FilterMode::Linear,
+                min_filter: wgpu::FilterMode::Nearest,
+                mipmap_filter: wgpu::FilterMode::Nearest,
+                ..Default::default()
+            });
+
+            Self { texture, view, sampler }
+        }
+
+        // Creates a colour texture sized to the surface that can both be rendered into
+        // and sampled from, e.g. the offscreen targets the post-process chain reads/writes
+        pub fn render_target(device: &wgpu::Device, config: &wgpu::SurfaceConfiguration, label: &str) -> Self {
+            let size = wgpu::Extent3d {
+                width: config.width,
+                height: config.height,
+                depth_or_array_layers: 1,
+            };
+
+            let texture = device.create_texture(&wgpu::TextureDescriptor {
+                label: Some(label),
+                size,
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: config.format,
+                usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+                view_formats: &[],
+            });
+
+            let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+            let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+                address_mode_u: wgpu::AddressMode::ClampToEdge,
+                address_mode_v: wgpu::AddressMode::ClampToEdge,
+                address_mode_w: wgpu::AddressMode::ClampToEdge,
+                mag_filter: wgpu::FilterMode::Linear,
+                min_filter: wgpu::FilterMode::Nearest,
+                mipmap_filter: wgpu::FilterMode::Nearest,
+                ..Default::default()
+            });
+
+            Self { texture, view, sampler }
+        }
+
+        // The layout that both the bind group below and the pipeline layout need to agree on
+        pub fn bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("Texture Bind Group Layout"),
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            multisampled: false,
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                        count: None,
+                    },
+                ],
+            })
+        }
+
+        pub fn bind_group(&self, device: &wgpu::Device, layout: &wgpu::BindGroupLayout) -> wgpu::BindGroup {
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Texture Bind Group"),
+                layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(&self.view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::Sampler(&self.sampler),
+                    },
+                ],
+            })
+        }
+    }
+}
+
+use texture::Texture;
+
+// Accumulates held keys, mouse button state, and cursor position across frames from raw
+// window events, so gameplay/camera code can poll continuous input each update instead
+// of reacting to one-shot `WindowEvent`s
+mod input {
+    use std::collections::HashSet;
+    use winit::{dpi::PhysicalPosition, event::MouseButton, keyboard::KeyCode};
+
+    #[derive(Default)]
+    pub struct InputState {
+        held_keys: HashSet<KeyCode>,
+        held_mouse_buttons: HashSet<MouseButton>,
+        cursor_position: PhysicalPosition<f64>,
+        previous_cursor_position: PhysicalPosition<f64>,
+    }
+
+    impl InputState {
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        pub fn process_keyboard(&mut self, key: KeyCode, pressed: bool) {
+            if pressed {
+                self.held_keys.insert(key);
+            } else {
+                self.held_keys.remove(&key);
+            }
+        }
+
+        pub fn process_mouse_button(&mut self, button: MouseButton, pressed: bool) {
+            if pressed {
+                self.held_mouse_buttons.insert(button);
+            } else {
+                self.held_mouse_buttons.remove(&button);
+            }
+        }
+
+        pub fn process_cursor_moved(&mut self, position: PhysicalPosition<f64>) {
+            self.cursor_position = position;
+        }
+
+        pub fn is_key_down(&self, key: KeyCode) -> bool {
+            self.held_keys.contains(&key)
+        }
+
+        pub fn is_mouse_button_down(&self, button: MouseButton) -> bool {
+            self.held_mouse_buttons.contains(&button)
+        }
+
+        pub fn cursor_position(&self) -> PhysicalPosition<f64> {
+            self.cursor_position
+        }
+
+        // How far the cursor has moved since the last call, then resets the baseline;
+        // callers poll this once per frame rather than accumulating raw move events themselves
+        pub fn mouse_delta(&mut self) -> (f64, f64) {
+            let delta = (
+                self.cursor_position.x - self.previous_cursor_position.x,
+                self.cursor_position.y - self.previous_cursor_position.y,
+            );
+            self.previous_cursor_position = self.cursor_position;
+            delta
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn key_down_tracks_press_and_release() {
+            let mut input = InputState::new();
+            assert!(!input.is_key_down(KeyCode::KeyW));
+
+            input.process_keyboard(KeyCode::KeyW, true);
+            assert!(input.is_key_down(KeyCode::KeyW));
+
+            input.process_keyboard(KeyCode::KeyW, false);
+            assert!(!input.is_key_down(KeyCode::KeyW));
+        }
+
+        #[test]
+        fn mouse_button_down_tracks_press_and_release() {
+            let mut input = InputState::new();
+            assert!(!input.is_mouse_button_down(MouseButton::Right));
+
+            input.process_mouse_button(MouseButton::Right, true);
+            assert!(input.is_mouse_button_down(MouseButton::Right));
+
+            input.process_mouse_button(MouseButton::Right, false);
+            assert!(!input.is_mouse_button_down(MouseButton::Right));
+        }
+
+        #[test]
+        fn mouse_delta_reports_movement_then_resets() {
+            let mut input = InputState::new();
+            input.process_cursor_moved(PhysicalPosition::new(10.0, 5.0));
+            assert_eq!(input.mouse_delta(), (10.0, 5.0));
+
+            // The baseline moved to (10, 5), so an unchanged position now reports no delta
+            assert_eq!(input.mouse_delta(), (0.0, 0.0));
+
+            input.process_cursor_moved(PhysicalPosition::new(12.0, 1.0));
+            assert_eq!(input.mouse_delta(), (2.0, -4.0));
+        }
+    }
+}
+
+use input::InputState;
+
+// Builds the view-projection matrix used to transform scene geometry into clip space,
+// and a small controller that steers the camera's eye position from held keys
+mod camera {
+    use winit::{event::MouseButton, keyboard::KeyCode};
+    use super::input::InputState;
+
+    // cgmath's clip space is [-1, 1] on Z, wgpu's is [0, 1], so every projection
+    // needs to be run through this correction matrix first
+    #[rustfmt::skip]
+    pub const OPENGL_TO_WGPU_MATRIX: cgmath::Matrix4<f32> = cgmath::Matrix4::new(
+        1.0, 0.0, 0.0, 0.0,
+        0.0, 1.0, 0.0, 0.0,
+        0.0, 0.0, 0.5, 0.0,
+        0.0, 0.0, 0.5, 1.0,
+    );
+
+    pub struct Camera {
+        pub eye: cgmath::Point3<f32>,
+        pub target: cgmath::Point3<f32>,
+        pub up: cgmath::Vector3<f32>,
+        pub aspect: f32,
+        pub fovy: f32,
+        pub znear: f32,
+        pub zfar: f32,
+    }
+
+    impl Camera {
+        pub fn build_view_projection_matrix(&self) -> cgmath::Matrix4<f32> {
+            let view = cgmath::Matrix4::look_at_rh(self.eye, self.target, self.up);
+            let proj = cgmath::perspective(cgmath::Deg(self.fovy), self.aspect, self.znear, self.zfar);
+
+            OPENGL_TO_WGPU_MATRIX * proj * view
+        }
+    }
+
+    // GPU-visible mirror of the camera's view-projection matrix, uploaded to a uniform buffer
+    #[repr(C)]
+    #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+    pub struct CameraUniform {
+        view_proj: [[f32; 4]; 4],
+    }
+
+    impl CameraUniform {
+        pub fn new() -> Self {
+            use cgmath::SquareMatrix;
+
+            Self {
+                view_proj: cgmath::Matrix4::identity().into(),
+            }
+        }
+
+        pub fn update_view_proj(&mut self, camera: &Camera) {
+            self.view_proj = camera.build_view_projection_matrix().into();
+        }
+    }
+
+    // Nudges the camera each frame based on whichever movement keys `InputState`
+    // currently reports as held, rather than tracking its own one-shot key events
+    pub struct CameraController {
+        speed: f32,
+    }
+
+    impl CameraController {
+        pub fn new(speed: f32) -> Self {
+            Self { speed }
+        }
+
+        pub fn update_camera(&self, camera: &mut Camera, input: &mut InputState) {
+            use cgmath::{InnerSpace, Rotation, Rotation3};
+
+            let forward_pressed = input.is_key_down(KeyCode::KeyW) || input.is_key_down(KeyCode::ArrowUp);
+            let backward_pressed = input.is_key_down(KeyCode::KeyS) || input.is_key_down(KeyCode::ArrowDown);
+            let left_pressed = input.is_key_down(KeyCode::KeyA) || input.is_key_down(KeyCode::ArrowLeft);
+            let right_pressed = input.is_key_down(KeyCode::KeyD) || input.is_key_down(KeyCode::ArrowRight);
+
+            let forward = camera.target - camera.eye;
+            let forward_norm = forward.normalize();
+            let forward_mag = forward.magnitude();
+
+            // Walk forward/backward, but never past the target, or the camera would flip direction
+            if forward_pressed && forward_mag > self.speed {
+                camera.eye += forward_norm * self.speed;
+            }
+            if backward_pressed {
+                camera.eye -= forward_norm * self.speed;
+            }
+
+            let right = forward_norm.cross(camera.up);
+
+            // Re-derive forward after strafing so the eye-to-target distance stays constant
+            let forward = camera.target - camera.eye;
+            let forward_mag = forward.magnitude();
+
+            if right_pressed {
+                camera.eye = camera.target - (forward + right * self.speed).normalize() * forward_mag;
+            }
+            if left_pressed {
+                camera.eye = camera.target - (forward - right * self.speed).normalize() * forward_mag;
+            }
+
+            // Orbits the eye around the target while the right mouse button is held,
+            // driven by the pointer's frame-to-frame delta rather than a one-shot event
+            let (delta_x, delta_y) = input.mouse_delta();
+            if input.is_mouse_button_down(MouseButton::Right) {
+                const SENSITIVITY: f32 = 0.005;
+                let yaw = cgmath::Rad(-delta_x as f32 * SENSITIVITY);
+                let pitch = cgmath::Rad(-delta_y as f32 * SENSITIVITY);
+
+                let offset = camera.eye - camera.target;
+                let yawed = cgmath::Quaternion::from_axis_angle(camera.up, yaw).rotate_vector(offset);
+                let right = yawed.normalize().cross(camera.up).normalize();
+                let pitched = cgmath::Quaternion::from_axis_angle(right, pitch).rotate_vector(yawed);
+
+                camera.eye = camera.target + pitched;
+            }
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use cgmath::InnerSpace;
+        use winit::dpi::PhysicalPosition;
+
+        fn test_camera() -> Camera {
+            Camera {
+                eye: (0.0, 0.0, 2.0).into(),
+                target: (0.0, 0.0, 0.0).into(),
+                up: cgmath::Vector3::unit_y(),
+                aspect: 1.0,
+                fovy: 45.0,
+                znear: 0.1,
+                zfar: 100.0,
+            }
+        }
+
+        #[test]
+        fn forward_key_moves_eye_toward_target() {
+            let controller = CameraController::new(0.2);
+            let mut camera = test_camera();
+            let mut input = InputState::new();
+            input.process_keyboard(KeyCode::KeyW, true);
+
+            let distance_before = (camera.eye - camera.target).magnitude();
+            controller.update_camera(&mut camera, &mut input);
+
+            assert!((camera.eye - camera.target).magnitude() < distance_before);
+        }
+
+        #[test]
+        fn no_keys_held_leaves_eye_unchanged() {
+            let controller = CameraController::new(0.2);
+            let mut camera = test_camera();
+            let mut input = InputState::new();
+
+            let eye_before = camera.eye;
+            controller.update_camera(&mut camera, &mut input);
+
+            assert_eq!(camera.eye, eye_before);
+        }
+
+        #[test]
+        fn orbit_only_applies_while_right_mouse_button_is_held() {
+            let controller = CameraController::new(0.2);
+            let mut camera = test_camera();
+            let mut input = InputState::new();
+            input.process_cursor_moved(PhysicalPosition::new(50.0, 0.0));
+
+            let eye_before = camera.eye;
+            controller.update_camera(&mut camera, &mut input);
+
+            // The cursor moved, but with no button held the orbit should not apply
+            assert_eq!(camera.eye, eye_before);
+        }
+
+        #[test]
+        fn orbit_moves_eye_while_right_mouse_button_is_held() {
+            let controller = CameraController::new(0.2);
+            let mut camera = test_camera();
+            let mut input = InputState::new();
+            input.process_mouse_button(MouseButton::Right, true);
+            input.process_cursor_moved(PhysicalPosition::new(50.0, 0.0));
+
+            let eye_before = camera.eye;
+            let distance_before = (camera.eye - camera.target).magnitude();
+            controller.update_camera(&mut camera, &mut input);
+
+            assert_ne!(camera.eye, eye_before);
+            // Orbiting should preserve the eye's distance from the target
+            assert!(((camera.eye - camera.target).magnitude() - distance_before).abs() < 1e-4);
+        }
+
+        #[test]
+        fn orbit_preserves_distance_for_the_non_perpendicular_default_camera() {
+            // Uses the same eye/target as the real runtime camera (`State::new`), where the
+            // eye-to-target offset isn't perpendicular to `up` — unlike `test_camera()` above,
+            // which makes `right` come out unit-length by construction and would mask a
+            // missing normalize() on it
+            let controller = CameraController::new(0.2);
+            let mut camera = Camera {
+                eye: (0.0, 1.0, 2.0).into(),
+                target: (0.0, 0.0, 0.0).into(),
+                up: cgmath::Vector3::unit_y(),
+                aspect: 1.0,
+                fovy: 45.0,
+                znear: 0.1,
+                zfar: 100.0,
+            };
+            let mut input = InputState::new();
+            input.process_mouse_button(MouseButton::Right, true);
+            input.process_cursor_moved(PhysicalPosition::new(50.0, 30.0));
+
+            let distance_before = (camera.eye - camera.target).magnitude();
+            controller.update_camera(&mut camera, &mut input);
+
+            assert!(((camera.eye - camera.target).magnitude() - distance_before).abs() < 1e-4);
+        }
+    }
+}
+
+use camera::{Camera, CameraController, CameraUniform};
+
+// Embed the default quad texture directly into the binary so the engine has
+// something to render without needing assets shipped alongside it
+const DIFFUSE_BYTES: &[u8] = include_bytes!("happy-tree.png");
+
 // Represents a vertex (point in 3D space)
 // needs to derive Copy so it can be copied into the buffer
 #[repr(C)]
@@ -13,6 +499,9 @@ struct Vertex {
 
     // The RGB color array
     color: [f32; 3],
+
+    // UV coordinates used to sample a texture at this vertex
+    tex_coords: [f32; 2],
 }
 
 impl Vertex {
@@ -40,18 +529,159 @@ impl Vertex {
                     shader_location: 1,
                     format: wgpu::VertexFormat::Float32x3
                 },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 3]>() as wgpu::BufferAddress * 2,
+                    shader_location: 2,
+                    format: wgpu::VertexFormat::Float32x2
+                },
             ],
         }
     }
 }
 
+// A lightweight entity-component store so geometry can be spawned and despawned at
+// runtime instead of living as module constants that require a recompile to change
+mod ecs {
+    use super::Vertex;
+
+    // A single renderable mesh: one entity's worth of vertex/index data
+    pub struct Mesh2d {
+        pub vertices: Vec<Vertex>,
+        pub indices: Vec<u16>,
+    }
+
+    // A stable handle to a spawned entity. The `generation` field is bumped every time
+    // a slot is despawned and reused, so a stale `Entity` from before a despawn can never
+    // be mistaken for whatever entity ends up occupying the same slot afterwards
+    #[derive(Clone, Copy, PartialEq, Eq, Debug)]
+    pub struct Entity {
+        index: usize,
+        generation: u32,
+    }
+
+    // One slot in the world's backing store: either a live mesh, or a free slot left
+    // behind by a despawn and waiting to be reused by a later spawn
+    struct Slot {
+        generation: u32,
+        mesh: Option<Mesh2d>,
+    }
+
+    // Holds every mesh entity currently in the scene, indexed by stable `Entity` handles
+    // rather than raw `Vec` position, so despawning one entity can never shift another
+    // entity's id out from under it
+    pub struct World {
+        slots: Vec<Slot>,
+        free_list: Vec<usize>,
+    }
+
+    impl World {
+        pub fn new() -> Self {
+            Self { slots: Vec::new(), free_list: Vec::new() }
+        }
+
+        // Adds a mesh entity to the scene, returning a handle that can later be used to
+        // despawn it. Reuses a free slot left by an earlier despawn when one is available
+        pub fn spawn(&mut self, mesh: Mesh2d) -> Entity {
+            if let Some(index) = self.free_list.pop() {
+                let slot = &mut self.slots[index];
+                slot.mesh = Some(mesh);
+                Entity { index, generation: slot.generation }
+            } else {
+                let index = self.slots.len();
+                self.slots.push(Slot { generation: 0, mesh: Some(mesh) });
+                Entity { index, generation: 0 }
+            }
+        }
+
+        // Removes a mesh entity from the scene. A no-op if the entity was already
+        // despawned, or if its generation no longer matches the slot's (i.e. the slot
+        // has since been reused by a different entity)
+        pub fn despawn(&mut self, entity: Entity) {
+            if let Some(slot) = self.slots.get_mut(entity.index) {
+                if slot.generation == entity.generation && slot.mesh.is_some() {
+                    slot.mesh = None;
+                    slot.generation = slot.generation.wrapping_add(1);
+                    self.free_list.push(entity.index);
+                }
+            }
+        }
+
+        pub fn meshes(&self) -> impl Iterator<Item = &Mesh2d> {
+            self.slots.iter().filter_map(|slot| slot.mesh.as_ref())
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn test_mesh() -> Mesh2d {
+            Mesh2d { vertices: Vec::new(), indices: Vec::new() }
+        }
+
+        #[test]
+        fn spawn_adds_a_mesh_and_returns_its_id() {
+            let mut world = World::new();
+            let id = world.spawn(test_mesh());
+
+            assert_eq!(id, Entity { index: 0, generation: 0 });
+            assert_eq!(world.meshes().count(), 1);
+        }
+
+        #[test]
+        fn despawn_removes_the_mesh_at_its_id() {
+            let mut world = World::new();
+            world.spawn(test_mesh());
+            let second = world.spawn(test_mesh());
+
+            world.despawn(second);
+
+            assert_eq!(world.meshes().count(), 1);
+        }
+
+        #[test]
+        fn despawn_out_of_bounds_is_a_no_op() {
+            let mut world = World::new();
+            world.spawn(test_mesh());
+
+            world.despawn(Entity { index: 5, generation: 0 });
+
+            assert_eq!(world.meshes().count(), 1);
+        }
+
+        #[test]
+        fn despawning_a_non_last_entity_leaves_the_others_resolvable() {
+            let mut world = World::new();
+            let first = world.spawn(Mesh2d { vertices: vec![Vertex { position: [1.0, 0.0, 0.0], color: [0.0; 3], tex_coords: [0.0; 2] }], indices: Vec::new() });
+            let second = world.spawn(Mesh2d { vertices: vec![Vertex { position: [2.0, 0.0, 0.0], color: [0.0; 3], tex_coords: [0.0; 2] }], indices: Vec::new() });
+            let third = world.spawn(Mesh2d { vertices: vec![Vertex { position: [3.0, 0.0, 0.0], color: [0.0; 3], tex_coords: [0.0; 2] }], indices: Vec::new() });
+
+            world.despawn(first);
+
+            let remaining: Vec<f32> = world.meshes().map(|mesh| mesh.vertices[0].position[0]).collect();
+            assert_eq!(remaining, vec![2.0, 3.0]);
+
+            // The surviving entities' original handles still resolve correctly: despawning
+            // them removes exactly the mesh they were spawned with, not a shifted neighbor
+            world.despawn(second);
+            let remaining: Vec<f32> = world.meshes().map(|mesh| mesh.vertices[0].position[0]).collect();
+            assert_eq!(remaining, vec![3.0]);
+
+            world.despawn(third);
+            assert_eq!(world.meshes().count(), 0);
+        }
+    }
+}
+
+use ecs::{Entity, Mesh2d, World};
+
 // A front facing triangle (to avoid being culled)
 const VERTICES: &[Vertex] = &[
-    Vertex { position: [-0.0868241, 0.49240386, 0.0], color: [0.5, 0.0, 0.5] }, // A
-    Vertex { position: [-0.49513406, 0.06958647, 0.0], color: [0.5, 0.0, 0.5] }, // B
-    Vertex { position: [-0.21918549, -0.44939706, 0.0], color: [0.5, 0.0, 0.5] }, // C
-    Vertex { position: [0.35966998, -0.3473291, 0.0], color: [0.5, 0.0, 0.5] }, // D
-    Vertex { position: [0.44147372, 0.2347359, 0.0], color: [0.5, 0.0, 0.5] }, // E
+    Vertex { position: [-0.0868241, 0.49240386, 0.0], color: [0.5, 0.0, 0.5], tex_coords: [0.4131759, 0.00759614] }, // A
+    Vertex { position: [-0.49513406, 0.06958647, 0.0], color: [0.5, 0.0, 0.5], tex_coords: [0.0048659444, 0.43041354] }, // B
+    Vertex { position: [-0.21918549, -0.44939706, 0.0], color: [0.5, 0.0, 0.5], tex_coords: [0.28081453, 0.949397] }, // C
+    Vertex { position: [0.35966998, -0.3473291, 0.0], color: [0.5, 0.0, 0.5], tex_coords: [0.85967, 0.84732914] }, // D
+    Vertex { position: [0.44147372, 0.2347359, 0.0], color: [0.5, 0.0, 0.5], tex_coords: [0.9414737, 0.2652641] }, // E
 ];
 
 // Indices to access repeated vertex data efficiently
@@ -61,6 +691,511 @@ const INDICES: &[u16] = &[
     2,3,4,
 ];
 
+// The pixel format used for the depth buffer
+// 32 bit float gives plenty of precision and is widely supported
+const DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
+
+// How often the simulation advances, independent of how fast the GPU can draw frames
+const FRAME_DURATION: Duration = Duration::from_millis(16);
+
+// Drives a GPU cellular-automaton simulation on two ping-pong storage buffers:
+// each tick reads the current generation and writes the next one into the other buffer
+mod compute {
+    pub const GRID_WIDTH: u32 = 64;
+    pub const GRID_HEIGHT: u32 = 64;
+
+    // A glider, seeded near the top-left corner of an otherwise empty grid
+    pub fn initial_grid() -> Vec<u32> {
+        let mut cells = vec![0u32; (GRID_WIDTH * GRID_HEIGHT) as usize];
+        for &(x, y) in &[(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)] {
+            cells[(y * GRID_WIDTH + x) as usize] = 1;
+        }
+        cells
+    }
+
+    pub struct Simulation {
+        pipeline: wgpu::ComputePipeline,
+        bind_groups: [wgpu::BindGroup; 2],
+        buffers: [wgpu::Buffer; 2],
+        current: usize,
+        view_pipeline: wgpu::RenderPipeline,
+        view_bind_group_layout: wgpu::BindGroupLayout,
+    }
+
+    impl Simulation {
+        pub fn new(device: &wgpu::Device, surface_format: wgpu::TextureFormat) -> Self {
+            let shader = device.create_shader_module(wgpu::include_wgsl!("compute.wgsl"));
+
+            let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("Compute Bind Group Layout"),
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Storage { read_only: true },
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Storage { read_only: false },
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                ],
+            });
+
+            let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Compute Pipeline Layout"),
+                bind_group_layouts: &[&bind_group_layout],
+                push_constant_ranges: &[],
+            });
+            let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some("Compute Pipeline"),
+                layout: Some(&pipeline_layout),
+                module: &shader,
+                entry_point: "cs_main",
+            });
+
+            // Both buffers start holding the same seed grid; the first dispatch reads
+            // buffer 0 and writes buffer 1, then the two swap roles every tick
+            let initial_grid = initial_grid();
+            let make_buffer = |label: &str| {
+                device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some(label),
+                    contents: bytemuck::cast_slice(&initial_grid),
+                    usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC | wgpu::BufferUsages::COPY_DST,
+                })
+            };
+            let buffers = [make_buffer("Grid Buffer A"), make_buffer("Grid Buffer B")];
+
+            let make_bind_group = |read_index: usize, write_index: usize| {
+                device.create_bind_group(&wgpu::BindGroupDescriptor {
+                    label: Some("Compute Bind Group"),
+                    layout: &bind_group_layout,
+                    entries: &[
+                        wgpu::BindGroupEntry {
+                            binding: 0,
+                            resource: buffers[read_index].as_entire_binding(),
+                        },
+                        wgpu::BindGroupEntry {
+                            binding: 1,
+                            resource: buffers[write_index].as_entire_binding(),
+                        },
+                    ],
+                })
+            };
+            let bind_groups = [make_bind_group(0, 1), make_bind_group(1, 0)];
+
+            // Lets `draw_overlay` sample whichever grid buffer the compute pass most
+            // recently wrote, so the simulation driven by `step` is visible on screen
+            let view_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("Grid View Bind Group Layout"),
+                entries: &[wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: true },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                }],
+            });
+
+            let view_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Grid View Pipeline Layout"),
+                bind_group_layouts: &[&view_bind_group_layout],
+                push_constant_ranges: &[],
+            });
+            let view_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some("Grid View Pipeline"),
+                layout: Some(&view_pipeline_layout),
+                vertex: wgpu::VertexState {
+                    module: &shader,
+                    // Drawn with 3 vertices and no vertex buffer, same full-screen-triangle
+                    // trick the post-process passes use
+                    entry_point: "vs_view",
+                    buffers: &[],
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: &shader,
+                    entry_point: "fs_view",
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format: surface_format,
+                        blend: Some(wgpu::BlendState::REPLACE),
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                }),
+                primitive: wgpu::PrimitiveState {
+                    topology: wgpu::PrimitiveTopology::TriangleList,
+                    strip_index_format: None,
+                    front_face: wgpu::FrontFace::Ccw,
+                    cull_mode: None,
+                    polygon_mode: wgpu::PolygonMode::Fill,
+                    unclipped_depth: false,
+                    conservative: false,
+                },
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState {
+                    count: 1,
+                    mask: !0,
+                    alpha_to_coverage_enabled: false,
+                },
+                multiview: None,
+            });
+
+            Self {
+                pipeline,
+                bind_groups,
+                buffers,
+                current: 0,
+                view_pipeline,
+                view_bind_group_layout,
+            }
+        }
+
+        // Encodes and submits one simulation step, then swaps which buffer is "current"
+        // so the render pass always samples the generation that was just written
+        pub fn step(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) {
+            let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Compute Encoder"),
+            });
+
+            {
+                let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                    label: Some("Compute Pass"),
+                    timestamp_writes: None,
+                });
+                compute_pass.set_pipeline(&self.pipeline);
+                compute_pass.set_bind_group(0, &self.bind_groups[self.current], &[]);
+                compute_pass.dispatch_workgroups(
+                    (GRID_WIDTH + 7) / 8,
+                    (GRID_HEIGHT + 7) / 8,
+                    1,
+                );
+            }
+
+            queue.submit(std::iter::once(encoder.finish()));
+            self.current = 1 - self.current;
+        }
+
+        // The buffer holding the generation that was most recently written
+        pub fn current_buffer(&self) -> &wgpu::Buffer {
+            &self.buffers[self.current]
+        }
+
+        // Draws the current generation into a small inset in the corner of `view`,
+        // leaving the rest of the frame untouched, so the simulation `step` advances
+        // each tick is actually visible rather than silently discarded
+        pub fn draw_overlay(&self, device: &wgpu::Device, encoder: &mut wgpu::CommandEncoder, view: &wgpu::TextureView) {
+            let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Grid View Bind Group"),
+                layout: &self.view_bind_group_layout,
+                entries: &[wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: self.current_buffer().as_entire_binding(),
+                }],
+            });
+
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Grid View Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        // Keep whatever was already drawn; the viewport below restricts
+                        // this pass to a small corner instead of covering the whole frame
+                        load: wgpu::LoadOp::Load,
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                occlusion_query_set: None,
+                timestamp_writes: None,
+            });
+
+            render_pass.set_viewport(10.0, 10.0, 150.0, 150.0, 0.0, 1.0);
+            render_pass.set_pipeline(&self.view_pipeline);
+            render_pass.set_bind_group(0, &bind_group, &[]);
+            render_pass.draw(0..3, 0..1);
+        }
+    }
+}
+
+use compute::Simulation;
+
+// Renders a debug text overlay (frame time, cursor position, entity count) on top of
+// the scene each frame, using an embedded TTF font rasterized by a glyph brush
+mod hud {
+    use std::time::Duration;
+    use winit::dpi::PhysicalPosition;
+    use wgpu_glyph::{ab_glyph, GlyphBrush, GlyphBrushBuilder, Section, Text};
+
+    const FONT_BYTES: &[u8] = include_bytes!("DejaVuSans.ttf");
+
+    pub struct Hud {
+        glyph_brush: GlyphBrush<()>,
+        staging_belt: wgpu::util::StagingBelt,
+    }
+
+    impl Hud {
+        pub fn new(device: &wgpu::Device, format: wgpu::TextureFormat) -> Self {
+            let font = ab_glyph::FontArc::try_from_slice(FONT_BYTES)
+                .expect("failed to parse embedded HUD font");
+            let glyph_brush = GlyphBrushBuilder::using_font(font).build(device, format);
+
+            Self {
+                glyph_brush,
+                // A handful of short debug lines per frame never comes close to this
+                staging_belt: wgpu::util::StagingBelt::new(1024),
+            }
+        }
+
+        // Queues the HUD text; nothing reaches the GPU until `draw` runs
+        pub fn queue(&mut self, frame_time: Duration, cursor: PhysicalPosition<f64>, entity_count: usize) {
+            let text = format!(
+                "frame: {:.2}ms\ncursor: ({:.0}, {:.0})\nentities: {}",
+                frame_time.as_secs_f64() * 1000.0,
+                cursor.x,
+                cursor.y,
+                entity_count,
+            );
+
+            self.glyph_brush.queue(Section {
+                screen_position: (10.0, 10.0),
+                text: vec![Text::new(&text)
+                    .with_color([1.0, 1.0, 1.0, 1.0])
+                    .with_scale(20.0)],
+                ..Section::default()
+            });
+        }
+
+        // Draws everything queued since the last call into `view` and marks the staging
+        // belt's buffers as uploaded; `recall` must still be called once submitted
+        pub fn draw(
+            &mut self,
+            device: &wgpu::Device,
+            encoder: &mut wgpu::CommandEncoder,
+            view: &wgpu::TextureView,
+            width: u32,
+            height: u32,
+        ) {
+            self.glyph_brush
+                .draw_queued(device, &mut self.staging_belt, encoder, view, width, height)
+                .expect("failed to draw HUD text");
+
+            self.staging_belt.finish();
+        }
+
+        // Frees staging belt buffers the GPU has finished reading; call only after the
+        // command buffer containing `draw`'s work has been submitted to the queue
+        pub fn recall(&mut self) {
+            self.staging_belt.recall();
+        }
+    }
+}
+
+use hud::Hud;
+
+// A chain of full-screen fragment passes that each sample the previous pass's output,
+// letting callers stack screen-space shaders (grayscale, gamma, scanlines, ...) like a
+// shader-preset pipeline between the scene render and the final surface present
+mod post_process {
+    use super::texture::Texture;
+
+    // Which built-in screen-space shader an effect pass runs; each maps to one fragment
+    // entry point in post_process.wgsl that shares the same full-screen-triangle vertex stage
+    #[derive(Clone, Copy)]
+    pub enum PostProcessEffect {
+        Grayscale,
+        Gamma,
+        Scanlines,
+    }
+
+    impl PostProcessEffect {
+        fn entry_point(self) -> &'static str {
+            match self {
+                PostProcessEffect::Grayscale => "fs_grayscale",
+                PostProcessEffect::Gamma => "fs_gamma",
+                PostProcessEffect::Scanlines => "fs_scanlines",
+            }
+        }
+    }
+
+    struct Pass {
+        pipeline: wgpu::RenderPipeline,
+    }
+
+    // Ordered list of post-process passes, plus the pieces needed to build more of them
+    // on the fly as callers push effects
+    pub struct PostProcessChain {
+        format: wgpu::TextureFormat,
+        bind_group_layout: wgpu::BindGroupLayout,
+        shader: wgpu::ShaderModule,
+
+        // Runs when no effects have been pushed yet, so the scene texture still makes
+        // it to the swapchain instead of the surface being left unwritten
+        passthrough: Pass,
+        passes: Vec<Pass>,
+    }
+
+    impl PostProcessChain {
+        pub fn new(device: &wgpu::Device, format: wgpu::TextureFormat) -> Self {
+            let shader = device.create_shader_module(wgpu::include_wgsl!("post_process.wgsl"));
+            let bind_group_layout = Texture::bind_group_layout(device);
+            let passthrough = Self::build_pass(device, &shader, &bind_group_layout, format, "fs_passthrough");
+
+            Self {
+                format,
+                bind_group_layout,
+                shader,
+                passthrough,
+                passes: Vec::new(),
+            }
+        }
+
+        fn build_pass(
+            device: &wgpu::Device,
+            shader: &wgpu::ShaderModule,
+            bind_group_layout: &wgpu::BindGroupLayout,
+            format: wgpu::TextureFormat,
+            entry_point: &str,
+        ) -> Pass {
+            let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Post Process Pipeline Layout"),
+                bind_group_layouts: &[bind_group_layout],
+                push_constant_ranges: &[],
+            });
+
+            let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some("Post Process Pipeline"),
+                layout: Some(&pipeline_layout),
+                vertex: wgpu::VertexState {
+                    module: shader,
+                    // Drawn with 3 vertices and no vertex buffer; post_process.wgsl derives
+                    // a full-screen triangle from the builtin vertex index
+                    entry_point: "vs_main",
+                    buffers: &[],
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: shader,
+                    entry_point,
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format,
+                        blend: Some(wgpu::BlendState::REPLACE),
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                }),
+                primitive: wgpu::PrimitiveState {
+                    topology: wgpu::PrimitiveTopology::TriangleList,
+                    strip_index_format: None,
+                    front_face: wgpu::FrontFace::Ccw,
+                    cull_mode: None,
+                    polygon_mode: wgpu::PolygonMode::Fill,
+                    unclipped_depth: false,
+                    conservative: false,
+                },
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState {
+                    count: 1,
+                    mask: !0,
+                    alpha_to_coverage_enabled: false,
+                },
+                multiview: None,
+            });
+
+            Pass { pipeline }
+        }
+
+        // Appends an effect to the end of the chain, building its pipeline up front so
+        // `run` never has to create GPU objects mid-frame
+        pub fn push_effect(&mut self, device: &wgpu::Device, effect: PostProcessEffect) {
+            let pass = Self::build_pass(device, &self.shader, &self.bind_group_layout, self.format, effect.entry_point());
+            self.passes.push(pass);
+        }
+
+        // Runs every configured pass, each sampling the previous pass's output: `scene` is
+        // the texture the geometry render pass wrote into, and `scratch` is a same-sized
+        // offscreen texture the passes ping-pong through before the last one targets
+        // `output_view`, the real swapchain view
+        pub fn run(
+            &self,
+            device: &wgpu::Device,
+            encoder: &mut wgpu::CommandEncoder,
+            scene: &Texture,
+            scratch: &Texture,
+            output_view: &wgpu::TextureView,
+        ) {
+            let passes: &[Pass] = if self.passes.is_empty() {
+                std::slice::from_ref(&self.passthrough)
+            } else {
+                &self.passes
+            };
+
+            let mut source = scene;
+            // Which offscreen texture the next intermediate pass (if any) should write
+            // into; starts at `scratch` since the first pass reads `scene`
+            let mut next_target_is_scratch = true;
+
+            for (index, pass) in passes.iter().enumerate() {
+                let is_last = index == passes.len() - 1;
+                let target_view = if is_last {
+                    output_view
+                } else if next_target_is_scratch {
+                    &scratch.view
+                } else {
+                    &scene.view
+                };
+
+                let bind_group = source.bind_group(device, &self.bind_group_layout);
+
+                {
+                    let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                        label: Some("Post Process Pass"),
+                        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                            view: target_view,
+                            resolve_target: None,
+                            ops: wgpu::Operations {
+                                load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                                store: wgpu::StoreOp::Store,
+                            },
+                        })],
+                        depth_stencil_attachment: None,
+                        occlusion_query_set: None,
+                        timestamp_writes: None,
+                    });
+
+                    render_pass.set_pipeline(&pass.pipeline);
+                    render_pass.set_bind_group(0, &bind_group, &[]);
+                    render_pass.draw(0..3, 0..1);
+                }
+
+                if !is_last {
+                    source = if next_target_is_scratch { scratch } else { scene };
+                    next_target_is_scratch = !next_target_is_scratch;
+                }
+            }
+        }
+    }
+}
+
+use post_process::{PostProcessChain, PostProcessEffect};
+
+// A mesh entity's vertex/index buffers, uploaded to the GPU from a `Mesh2d` component
+struct GpuMesh {
+    vertex_buffer: wgpu::Buffer,
+    index_buffer: wgpu::Buffer,
+    num_indices: u32,
+}
+
 // Represents the application state
 struct State<'a> {
     surface: wgpu::Surface<'a>,
@@ -71,10 +1206,23 @@ struct State<'a> {
     clear_color: wgpu::Color,
     window: &'a Window,
     render_pipeline: wgpu::RenderPipeline,
-    vertex_buffer: wgpu::Buffer,
-    num_vertices: u32,
-    index_buffer: wgpu::Buffer,
-    num_indices: u32,
+    world: World,
+    meshes: Vec<GpuMesh>,
+    depth_texture: wgpu::Texture,
+    depth_view: wgpu::TextureView,
+    diffuse_bind_group: wgpu::BindGroup,
+    camera: Camera,
+    camera_controller: CameraController,
+    camera_uniform: CameraUniform,
+    camera_buffer: wgpu::Buffer,
+    camera_bind_group: wgpu::BindGroup,
+    simulation: Simulation,
+    hud: Hud,
+    input_state: InputState,
+    last_frame_instant: Instant,
+    scene_texture: Texture,
+    post_process_scratch: Texture,
+    post_process: PostProcessChain,
 }
 
 impl State<'_> {
@@ -129,11 +1277,61 @@ impl State<'_> {
         };
         surface.configure(&device, &config);
 
+        // Load the default diffuse texture and the bind group that exposes it to the shader
+        let diffuse_texture = Texture::from_bytes(&device, &queue, DIFFUSE_BYTES, "happy-tree.png")
+            .expect("failed to load embedded diffuse texture");
+        let texture_bind_group_layout = Texture::bind_group_layout(&device);
+        let diffuse_bind_group = diffuse_texture.bind_group(&device, &texture_bind_group_layout);
+
+        // Set up the camera and its uniform buffer so the vertex shader can transform
+        // geometry by a real view-projection matrix instead of drawing in raw clip space
+        let camera = Camera {
+            eye: (0.0, 1.0, 2.0).into(),
+            target: (0.0, 0.0, 0.0).into(),
+            up: cgmath::Vector3::unit_y(),
+            aspect: config.width as f32 / config.height as f32,
+            fovy: 45.0,
+            znear: 0.1,
+            zfar: 100.0,
+        };
+        let camera_controller = CameraController::new(0.05);
+
+        let mut camera_uniform = CameraUniform::new();
+        camera_uniform.update_view_proj(&camera);
+
+        let camera_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Camera Buffer"),
+            contents: bytemuck::cast_slice(&[camera_uniform]),
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+        });
+
+        let camera_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Camera Bind Group Layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+        let camera_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Camera Bind Group"),
+            layout: &camera_bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: camera_buffer.as_entire_binding(),
+            }],
+        });
+
         // Configure the render pipeline
         let shader = device.create_shader_module(wgpu::include_wgsl!("shader.wgsl"));
         let render_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
             label: Some("Render Pipeline Layout"),
-            bind_group_layouts: &[],
+            bind_group_layouts: &[&texture_bind_group_layout, &camera_bind_group_layout],
             push_constant_ranges: &[],
         });
         let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
@@ -189,7 +1387,18 @@ impl State<'_> {
                 unclipped_depth: false,
                 conservative: false,
             },
-            depth_stencil: None,
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: DEPTH_FORMAT,
+
+                // Let geometry write its depth into the buffer so later draws can test against it
+                depth_write_enabled: true,
+
+                // Keep a fragment if it's at least as close as what's already there, so
+                // coplanar geometry drawn in the same pass doesn't flicker
+                depth_compare: wgpu::CompareFunction::LessEqual,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
             multisample: wgpu::MultisampleState {
                 count: 1,
                 mask: !0,
@@ -198,22 +1407,29 @@ impl State<'_> {
             multiview: None,
         });
 
-        // Use the device to create a vertex buffer to store vertex data
-        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Vertex Buffer"),
-            contents: bytemuck::cast_slice(VERTICES),
-            usage: wgpu::BufferUsages::VERTEX
-        });
+        let (depth_texture, depth_view) = Self::create_depth_texture(&device, &config);
+
+        // Scene geometry renders into this offscreen target instead of straight to the
+        // swapchain, so the post-process chain has something to sample; `post_process_scratch`
+        // is the second buffer passes ping-pong through when more than one effect is chained
+        let scene_texture = Texture::render_target(&device, &config, "Scene Texture");
+        let post_process_scratch = Texture::render_target(&device, &config, "Post Process Scratch Texture");
+        let post_process = PostProcessChain::new(&device, config.format);
 
-        // use the device to create a index buffer to store index data, which will be used to access repeated vertex data
-        let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Index Buffer"),
-            contents: bytemuck::cast_slice(INDICES),
-            usage: wgpu::BufferUsages::INDEX
+        // Seed the scene with the original triangle fan as its first entity, rather
+        // than binding VERTICES/INDICES directly to a single hardcoded buffer pair
+        let mut world = World::new();
+        world.spawn(Mesh2d {
+            vertices: VERTICES.to_vec(),
+            indices: INDICES.to_vec(),
         });
+        let meshes = Self::upload_meshes(&device, &world);
 
-        let num_vertices = VERTICES.len() as u32;
-        let num_indices = INDICES.len() as u32;
+        // Sets up the ping-pong grid simulation that State::update advances each tick
+        let simulation = Simulation::new(&device, config.format);
+
+        // Sets up the debug text overlay that State::render queues and draws every frame
+        let hud = Hud::new(&device, config.format);
 
         State {
             surface,
@@ -234,13 +1450,97 @@ impl State<'_> {
             // The render pipeline
             render_pipeline,
 
-            vertex_buffer,
-            num_vertices,
-            index_buffer,
-            num_indices,
+            world,
+            meshes,
+            depth_texture,
+            depth_view,
+            diffuse_bind_group,
+            camera,
+            camera_controller,
+            camera_uniform,
+            camera_buffer,
+            camera_bind_group,
+            simulation,
+            hud,
+            input_state: InputState::new(),
+            last_frame_instant: Instant::now(),
+            scene_texture,
+            post_process_scratch,
+            post_process,
         }
     }
 
+    // Appends a screen-space effect to the post-process chain, e.g. letting users stack
+    // grayscale, gamma correction, or CRT-style scanlines like a shader preset pipeline
+    pub fn push_post_process_effect(&mut self, effect: PostProcessEffect) {
+        self.post_process.push_effect(&self.device, effect);
+    }
+
+    // Adds a mesh entity to the scene and uploads its GPU buffers, returning an id that
+    // can later be passed to `despawn_mesh` — so callers can grow the scene at runtime
+    // instead of editing the `VERTICES`/`INDICES` constants and recompiling
+    pub fn spawn_mesh(&mut self, mesh: Mesh2d) -> Entity {
+        let entity = self.world.spawn(mesh);
+        self.meshes = Self::upload_meshes(&self.device, &self.world);
+        entity
+    }
+
+    // Removes a mesh entity from the scene and re-uploads the remaining GPU buffers
+    pub fn despawn_mesh(&mut self, entity: Entity) {
+        self.world.despawn(entity);
+        self.meshes = Self::upload_meshes(&self.device, &self.world);
+    }
+
+    // Creates the depth texture and its view, sized to match the surface
+    // Called on startup and again any time the surface is resized, since the
+    // depth buffer has to stay the same dimensions as the color attachment
+    fn create_depth_texture(device: &wgpu::Device, config: &wgpu::SurfaceConfiguration) -> (wgpu::Texture, wgpu::TextureView) {
+        let size = wgpu::Extent3d {
+            width: config.width,
+            height: config.height,
+            depth_or_array_layers: 1,
+        };
+
+        let depth_texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Depth Texture"),
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: DEPTH_FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+
+        let depth_view = depth_texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        (depth_texture, depth_view)
+    }
+
+    // Builds one GPU buffer pair per entity in the world so `render` can draw
+    // each mesh independently instead of assuming a single hardcoded buffer pair
+    fn upload_meshes(device: &wgpu::Device, world: &World) -> Vec<GpuMesh> {
+        world.meshes().map(|mesh| {
+            let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Vertex Buffer"),
+                contents: bytemuck::cast_slice(&mesh.vertices),
+                usage: wgpu::BufferUsages::VERTEX,
+            });
+
+            let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Index Buffer"),
+                contents: bytemuck::cast_slice(&mesh.indices),
+                usage: wgpu::BufferUsages::INDEX,
+            });
+
+            GpuMesh {
+                vertex_buffer,
+                index_buffer,
+                num_indices: mesh.indices.len() as u32,
+            }
+        }).collect()
+    }
+
     pub fn window(&self) -> &Window {
         &self.window
     }
@@ -252,22 +1552,28 @@ impl State<'_> {
             self.config.width = new_size.width;
             self.config.height = new_size.height;
             self.surface.configure(&self.device, &self.config);
-        }
-    }
 
-    fn input(&mut self, position: PhysicalPosition<f64>) -> bool {
-        self.clear_color = wgpu::Color {
-            a: 1.0,
-            r: position.x as f64 / self.size.width as f64,
-            g: position.y as f64 / self.size.height as f64,
-            b: 0.3,
-        };
+            // The depth buffer has to match the new surface dimensions or wgpu will panic
+            let (depth_texture, depth_view) = Self::create_depth_texture(&self.device, &self.config);
+            self.depth_texture = depth_texture;
+            self.depth_view = depth_view;
+
+            // The offscreen scene/post-process textures have to match the new surface
+            // dimensions too, or the post-process passes would sample a stale-sized texture
+            self.scene_texture = Texture::render_target(&self.device, &self.config, "Scene Texture");
+            self.post_process_scratch = Texture::render_target(&self.device, &self.config, "Post Process Scratch Texture");
 
-        true
+            // Keep the projection matrix matching the window's new proportions
+            self.camera.aspect = self.config.width as f32 / self.config.height as f32;
+        }
     }
 
     fn update(&mut self){
-        
+        self.camera_controller.update_camera(&mut self.camera, &mut self.input_state);
+        self.camera_uniform.update_view_proj(&self.camera);
+        self.queue.write_buffer(&self.camera_buffer, 0, bytemuck::cast_slice(&[self.camera_uniform]));
+
+        self.simulation.step(&self.device, &self.queue);
     }
 
     fn render(&mut self) -> Result<(), wgpu::SurfaceError>{
@@ -291,8 +1597,10 @@ impl State<'_> {
 
                 // Describes where we are going to draw color to (in this case it's the defined texture view)
                 color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    // Which texture view to save the color to
-                    view: &view,
+                    // Scene geometry renders into the offscreen scene texture rather than
+                    // the swapchain view directly, so the post-process chain below has
+                    // something to sample before the frame actually reaches the screen
+                    view: &self.scene_texture.view,
 
                     resolve_target: None,
 
@@ -305,7 +1613,15 @@ impl State<'_> {
                         store: wgpu::StoreOp::Store
                     }
                 })],
-                depth_stencil_attachment: None,
+                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                    view: &self.depth_view,
+                    depth_ops: Some(wgpu::Operations {
+                        // Reset every fragment to the far plane before the pass draws into it
+                        load: wgpu::LoadOp::Clear(1.0),
+                        store: wgpu::StoreOp::Store,
+                    }),
+                    stencil_ops: None,
+                }),
                 occlusion_query_set: None,
                 timestamp_writes: None
             });
@@ -313,20 +1629,45 @@ impl State<'_> {
             // Sets the render pipeline for the render pass
             render_pass.set_pipeline(&self.render_pipeline);
 
-            // Set the vertex buffer before drawing
-            render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+            // Bind the diffuse texture at group 0, matching the layout baked into the pipeline
+            render_pass.set_bind_group(0, &self.diffuse_bind_group, &[]);
 
-            // Set the index buffer before drawing
-            render_pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+            // Bind the camera's view-projection matrix at group 1
+            render_pass.set_bind_group(1, &self.camera_bind_group, &[]);
 
-            // Draws primitives
-            render_pass.draw_indexed(0..self.num_indices, 0, 0..1);
+            // Every entity in the world gets its own draw call against its own buffers
+            for mesh in &self.meshes {
+                render_pass.set_vertex_buffer(0, mesh.vertex_buffer.slice(..));
+                render_pass.set_index_buffer(mesh.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+                render_pass.draw_indexed(0..mesh.num_indices, 0, 0..1);
+            }
         }
 
+        // Runs the configured screen-space effect chain, sampling the scene texture (and
+        // each other's output in turn) with the last pass landing on the real swapchain view
+        self.post_process.run(&self.device, &mut encoder, &self.scene_texture, &self.post_process_scratch, &view);
+
+        // Draws the ping-pong grid simulation into a corner inset so the generations
+        // `State::update` steps each tick are actually visible on screen
+        self.simulation.draw_overlay(&self.device, &mut encoder, &view);
+
+        // Debug HUD overlay: frame time, cursor position, and live entity count, drawn
+        // directly onto the swapchain view so post-process effects never touch the text
+        let now = Instant::now();
+        let frame_time = now.duration_since(self.last_frame_instant);
+        self.last_frame_instant = now;
+
+        self.hud.queue(frame_time, self.input_state.cursor_position(), self.world.meshes().count());
+        self.hud.draw(&self.device, &mut encoder, &view, self.config.width, self.config.height);
+
         // Publish and process the command
         self.queue.submit(std::iter::once(encoder.finish()));
         output.present();
 
+        // Only safe to recall the staging belt's buffers once the submission above
+        // has handed their contents off to the GPU
+        self.hud.recall();
+
         Ok(())
     }
 }
@@ -337,7 +1678,23 @@ pub async fn run() {
     let window = WindowBuilder::new().build(&event_loop).expect("failed to create window");
 
     let mut state = State::new(&window).await;
-    
+
+    // Tracks when the simulation is next due to advance, independent of how
+    // often the window system hands us redraw/input events
+    let mut next_frame_time = Instant::now() + FRAME_DURATION;
+
+    // Entities spawned at runtime via the "T"/"Y" demo keys below, tracked so "Y" has
+    // something to despawn
+    let mut spawned_entities: Vec<Entity> = Vec::new();
+
+    // Cycles through the post-process chain's built-in effects each time "P" is pressed
+    const DEMO_POST_PROCESS_EFFECTS: [PostProcessEffect; 3] = [
+        PostProcessEffect::Grayscale,
+        PostProcessEffect::Gamma,
+        PostProcessEffect::Scanlines,
+    ];
+    let mut next_post_process_effect = 0usize;
+
     let _ = event_loop.run(move |event, event_loop_window_target|{
         match event {
             Event::WindowEvent { 
@@ -352,14 +1709,62 @@ pub async fn run() {
             } => {
                 event_loop_window_target.exit();    
             }
-            Event::WindowEvent { 
+            Event::WindowEvent {
                 event: WindowEvent::CursorMoved {
                     position,
                     ..
                 },
                 ..
             } => {
-                state.input(position);
+                state.input_state.process_cursor_moved(position);
+            }
+            Event::WindowEvent {
+                event: WindowEvent::KeyboardInput {
+                    event: KeyEvent {
+                        physical_key: PhysicalKey::Code(keycode),
+                        state: key_state,
+                        repeat,
+                        ..
+                    },
+                    ..
+                },
+                ..
+            } => {
+                state.input_state.process_keyboard(keycode, key_state.is_pressed());
+
+                // Demo bindings exercising the runtime spawn/despawn and post-process
+                // APIs: held-key repeats are ignored so each press acts exactly once
+                if key_state.is_pressed() && !repeat {
+                    match keycode {
+                        KeyCode::KeyT => {
+                            let entity = state.spawn_mesh(Mesh2d {
+                                vertices: VERTICES.to_vec(),
+                                indices: INDICES.to_vec(),
+                            });
+                            spawned_entities.push(entity);
+                        }
+                        KeyCode::KeyY => {
+                            if let Some(entity) = spawned_entities.pop() {
+                                state.despawn_mesh(entity);
+                            }
+                        }
+                        KeyCode::KeyP => {
+                            state.push_post_process_effect(DEMO_POST_PROCESS_EFFECTS[next_post_process_effect]);
+                            next_post_process_effect = (next_post_process_effect + 1) % DEMO_POST_PROCESS_EFFECTS.len();
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            Event::WindowEvent {
+                event: WindowEvent::MouseInput {
+                    state: button_state,
+                    button,
+                    ..
+                },
+                ..
+            } => {
+                state.input_state.process_mouse_button(button, button_state.is_pressed());
             }
             Event::WindowEvent {
                 event: WindowEvent::Resized(physical_size),
@@ -382,7 +1787,13 @@ pub async fn run() {
                 }
             }
             Event::AboutToWait =>{
-                state.window.request_redraw()
+                // Only advance/redraw once the fixed-rate deadline has actually passed,
+                // rather than requesting a new frame as fast as the GPU can churn them out
+                if Instant::now() >= next_frame_time {
+                    next_frame_time += FRAME_DURATION;
+                    state.window.request_redraw();
+                }
+                event_loop_window_target.set_control_flow(ControlFlow::WaitUntil(next_frame_time));
             }
             _ => ()
         }